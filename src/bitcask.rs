@@ -2,7 +2,8 @@
 //!
 //! ## Example:
 //! ```
-//! let mut writer = Writer::new("/tmp/yoted".to_string()).expect("Should open a writer");
+//! # use bitcask::bitcask::{Writer, Entry};
+//! let mut writer = Writer::new("/tmp/yoted".to_string(), None).expect("Should open a writer");
 //!
 //! let key = "Hello".as_bytes().to_vec();
 //! let value = "Yoted".as_bytes().to_vec();
@@ -15,8 +16,59 @@ use std::{collections::HashMap, convert::TryInto, error::Error, fs::{File, OpenO
 
 use crc::{Crc, CRC_64_ECMA_182};
 
+use aes_gcm::{aead::{Aead, Payload}, Aes256Gcm, KeyInit, Nonce};
+use argon2::Argon2;
+use chacha20poly1305::ChaCha20Poly1305;
+use password_hash::{
+    rand_core::{OsRng, RngCore},
+    SaltString,
+};
+
 use crate::util;
 
+/// Length of the per-entry AEAD nonce in bytes
+const NONCE_LEN: usize = 12;
+/// Self-describing file signature, borrowing the PNG header trick: a non-ASCII
+/// first byte so the file isn't mistaken for text, an ASCII tag, and a CR-LF
+/// pair a mangled text-mode transfer would corrupt.
+const MAGIC: [u8; 8] = [0x89, b'B', b'I', b'T', b'C', b'K', b'\r', b'\n'];
+/// Current on-disk format version
+const FORMAT_VERSION: u8 = 2;
+/// The first signed layout: entries with the encryption, compression, and nonce
+/// fields but a single whole-entry CRC, no per-chunk checksum table.
+const VERSION_V1: u8 = 1;
+/// The pre-header layout: no signature, and entries without the encryption,
+/// compression, and nonce fields. Detected by the absence of our signature.
+const LEGACY_VERSION: u8 = 0;
+/// Length of the signature + version header at offset 0 of a current-format file
+const HEADER_LEN: u64 = MAGIC.len() as u64 + 1;
+/// Value stored verbatim, no compression
+pub const COMPRESSION_NONE: u8 = 0;
+/// Value stored LZ4-compressed (size-prepended frame)
+pub const COMPRESSION_LZ4: u8 = 1;
+/// Largest checksum chunk-size log a `usize` shift can represent; a byte beyond
+/// this is a corrupt header, not a real configuration.
+const MAX_CHUNK_SIZE_LOG: u8 = 63;
+
+/// Reverse [`Entry::compressed_value`]: inflate `stored` per its `compression` byte.
+fn decompress(compression: u8, stored: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match compression {
+        COMPRESSION_NONE => Ok(stored.to_vec()),
+        COMPRESSION_LZ4 => Ok(lz4_flex::decompress_size_prepended(stored)?),
+        other => Err(UnknownCompressionError(other).into()),
+    }
+}
+
+/// An entry carried a compression byte we don't understand
+#[derive(Debug)]
+pub struct UnknownCompressionError(u8);
+impl Error for UnknownCompressionError {}
+impl fmt::Display for UnknownCompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown compression type byte {}", self.0)
+    }
+}
+
 /// A seek only pointer into our logs
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct IndexValue {
@@ -63,6 +115,73 @@ impl fmt::Display for IndexKeyNotFoundError {
     }
 }
 
+/// The stored CRC64 of a record didn't match the bytes we read back
+#[derive(Debug)]
+pub struct ChecksumMismatchError {
+    expected: u64,
+    actual: u64,
+}
+impl Error for ChecksumMismatchError {}
+impl fmt::Display for ChecksumMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checksum mismatch, expected {:#018x} but recomputed {:#018x}",
+            self.expected, self.actual
+        )
+    }
+}
+
+/// One chunk's stored CRC64 didn't match the bytes we read back, naming the
+/// chunk so a caller can tell which region of a large value is corrupt.
+#[derive(Debug)]
+pub struct ChunkChecksumMismatchError {
+    chunk: usize,
+    expected: u64,
+    actual: u64,
+}
+impl Error for ChunkChecksumMismatchError {}
+impl fmt::Display for ChunkChecksumMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checksum mismatch in chunk {}, expected {:#018x} but recomputed {:#018x}",
+            self.chunk, self.expected, self.actual
+        )
+    }
+}
+
+/// A record's chunk-size byte named a shift that wouldn't fit a `usize`, i.e. a
+/// corrupt header rather than a real chunk configuration
+#[derive(Debug)]
+pub struct InvalidChunkSizeError(u8);
+impl Error for InvalidChunkSizeError {}
+impl fmt::Display for InvalidChunkSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid checksum chunk size log {}", self.0)
+    }
+}
+
+/// A data file announced a format version newer than this build understands
+#[derive(Debug)]
+pub struct UnsupportedVersionError(u8);
+impl Error for UnsupportedVersionError {}
+impl fmt::Display for UnsupportedVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "on-disk format version {} is newer than supported version {}",
+            self.0, FORMAT_VERSION
+        )
+    }
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Index {
     pub fn new() -> Self {
         Self {
@@ -82,21 +201,163 @@ impl Index {
             None => Err(IndexKeyNotFoundError(key as Vec<u8>)),
         }
     }
+
+    /// Drop a key, e.g. when its newest log entry is a tombstone
+    pub fn remove(&mut self, key: &[u8]) -> Option<IndexValue> {
+        self.keys.remove(key)
+    }
+
+    /// Iterate the live key → pointer mappings (used to dump hint files)
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, Vec<u8>, IndexValue> {
+        self.keys.iter()
+    }
 }
 
 /// CRC64 digester
 pub const CRC: Crc<u64> = Crc::<u64>::new(&CRC_64_ECMA_182);
 
+/// Which AEAD cipher an entry's value is sealed with on disk.
+///
+/// Stored as one byte per [`Entry`] so a reader can choose the matching cipher
+/// (or skip decryption entirely) without any side metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionType {
+    None,
+    AesGcm,
+    Chacha20Poly1305,
+}
+
+impl EncryptionType {
+    fn as_byte(self) -> u8 {
+        match self {
+            EncryptionType::None => 0,
+            EncryptionType::AesGcm => 1,
+            EncryptionType::Chacha20Poly1305 => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, UnknownEncryptionError> {
+        match byte {
+            0 => Ok(EncryptionType::None),
+            1 => Ok(EncryptionType::AesGcm),
+            2 => Ok(EncryptionType::Chacha20Poly1305),
+            other => Err(UnknownEncryptionError(other)),
+        }
+    }
+}
+
+/// An entry's encryption byte didn't match any cipher we know
+#[derive(Debug)]
+pub struct UnknownEncryptionError(u8);
+impl Error for UnknownEncryptionError {}
+impl fmt::Display for UnknownEncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown encryption type byte {}", self.0)
+    }
+}
+
+/// Sealing or opening a value failed — a wrong passphrase or tampered bytes
+#[derive(Debug)]
+pub struct CryptoError(&'static str);
+impl Error for CryptoError {}
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AEAD failure: {}", self.0)
+    }
+}
+
+/// A 256-bit key derived from the user's passphrase together with the cipher it
+/// feeds. The key is stretched with Argon2 over a salt the [`Writer`] keeps next
+/// to the data, so reopening with the same passphrase yields the same key.
+#[derive(Clone)]
+pub struct Crypto {
+    encryption: EncryptionType,
+    key: [u8; 32],
+}
+
+impl Crypto {
+    /// Stretch `passphrase` into a key for `encryption` using the stored `salt`.
+    pub fn derive(
+        passphrase: &str,
+        salt: &SaltString,
+        encryption: EncryptionType,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt.as_str().as_bytes(), &mut key)
+            .map_err(|_| CryptoError("could not derive key"))?;
+        Ok(Self { encryption, key })
+    }
+
+    /// Seal `plaintext` under `nonce`, binding `aad` as associated data so a
+    /// tampered header fails the tag check. The tag is appended to the output.
+    fn seal(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg: plaintext, aad };
+        match self.encryption {
+            EncryptionType::None => Ok(plaintext.to_vec()),
+            EncryptionType::AesGcm => Aes256Gcm::new_from_slice(&self.key)
+                .map_err(|_| CryptoError("bad key length"))?
+                .encrypt(nonce, payload)
+                .map_err(|_| CryptoError("encrypt failed")),
+            EncryptionType::Chacha20Poly1305 => ChaCha20Poly1305::new_from_slice(&self.key)
+                .map_err(|_| CryptoError("bad key length"))?
+                .encrypt(nonce, payload)
+                .map_err(|_| CryptoError("encrypt failed")),
+        }
+    }
+
+    /// Open the AEAD `ciphertext` sealed under `nonce` with the same `aad`.
+    fn open(
+        &self,
+        nonce: &[u8; NONCE_LEN],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg: ciphertext, aad };
+        match self.encryption {
+            EncryptionType::None => Ok(ciphertext.to_vec()),
+            EncryptionType::AesGcm => Aes256Gcm::new_from_slice(&self.key)
+                .map_err(|_| CryptoError("bad key length"))?
+                .decrypt(nonce, payload)
+                .map_err(|_| CryptoError("authentication failed")),
+            EncryptionType::Chacha20Poly1305 => ChaCha20Poly1305::new_from_slice(&self.key)
+                .map_err(|_| CryptoError("bad key length"))?
+                .decrypt(nonce, payload)
+                .map_err(|_| CryptoError("authentication failed")),
+        }
+    }
+}
+
 /// An entry in our log which can be read and written to our log
 #[derive(Debug, Clone)]
 pub struct Entry {
     checksum: u64,
     active: bool,
+    encryption: u8,
+    compression: u8,
+
+    /// Log-2 of the checksum chunk size; `0` keeps a single whole-entry CRC,
+    /// any larger value splits the stored value into `1 << chunk_size_log`-byte
+    /// chunks each carrying its own checksum in the table below.
+    chunk_size_log: u8,
 
     timestamp: u128,
     key_size: usize,
     value_size: usize,
 
+    /// Per-entry AEAD nonce; all zeroes when the entry isn't encrypted
+    nonce: [u8; NONCE_LEN],
+
+    /// CRC64 of each stored value chunk, empty unless `chunk_size_log` is set
+    chunk_checksums: Vec<u64>,
+
     pub key: Vec<u8>,
     pub value: Vec<u8>,
 }
@@ -108,15 +369,18 @@ impl Entry {
         let timestamp = util::get_micros_since_epoch();
         let key_size = key.len();
         let value_size = value.len();
-        let key = key;
-        let value = value;
 
         let mut new_entry = Self {
             checksum: 0,
             active,
+            encryption: EncryptionType::None.as_byte(),
+            compression: COMPRESSION_NONE,
+            chunk_size_log: 0,
             timestamp,
             key_size,
             value_size,
+            nonce: [0u8; NONCE_LEN],
+            chunk_checksums: Vec::new(),
             key,
             value,
         };
@@ -131,6 +395,103 @@ impl Entry {
     pub fn calculate_checksum(&mut self) -> u64 {
         let mut digest = CRC.digest();
 
+        digest.update(if self.active { &[1] } else { &[0] });
+        digest.update(&[self.encryption]);
+        digest.update(&[self.compression]);
+        digest.update(&[self.chunk_size_log]);
+        digest.update(&self.timestamp.to_le_bytes());
+        digest.update(&self.key_size.to_le_bytes());
+        digest.update(&self.value_size.to_le_bytes());
+        digest.update(&self.nonce);
+        digest.update(&self.key);
+
+        // With a chunk table the whole-value bytes are covered by the per-chunk
+        // CRCs instead, so the entry CRC only guards the header and that table —
+        // a read never has to rehash the whole value just to trust the metadata.
+        if self.chunk_size_log == 0 {
+            digest.update(&self.value);
+        } else {
+            for crc in &self.chunk_checksums {
+                digest.update(&crc.to_le_bytes());
+            }
+        }
+
+        digest.finalize()
+    }
+
+    /// CRC64 over the [`VERSION_V1`] field set, which predates the chunk-size
+    /// byte and per-chunk table. Used when verifying records read back from a
+    /// file written before chunked checksums existed.
+    pub fn calculate_checksum_v1(&self) -> u64 {
+        let mut digest = CRC.digest();
+
+        digest.update(if self.active { &[1] } else { &[0] });
+        digest.update(&[self.encryption]);
+        digest.update(&[self.compression]);
+        digest.update(&self.timestamp.to_le_bytes());
+        digest.update(&self.key_size.to_le_bytes());
+        digest.update(&self.value_size.to_le_bytes());
+        digest.update(&self.nonce);
+        digest.update(&self.key);
+        digest.update(&self.value);
+
+        digest.finalize()
+    }
+
+    /// Split the stored value into `1 << chunk_size_log`-byte chunks and CRC each
+    /// one; empty (and so a no-op) unless a chunk size is configured.
+    fn compute_chunk_checksums(&self) -> Vec<u64> {
+        if self.chunk_size_log == 0 {
+            return Vec::new();
+        }
+        let chunk_size = 1usize << self.chunk_size_log;
+        self.value
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let mut digest = CRC.digest();
+                digest.update(chunk);
+                digest.finalize()
+            })
+            .collect()
+    }
+
+    /// Verify each stored per-chunk CRC against the value bytes, pinpointing the
+    /// first chunk that disagrees so a caller knows which region is corrupt.
+    fn verify_chunks(&self) -> Result<(), ChunkChecksumMismatchError> {
+        let chunk_size = 1usize << self.chunk_size_log;
+        for (index, &expected) in self.chunk_checksums.iter().enumerate() {
+            let start = index * chunk_size;
+            let end = (start + chunk_size).min(self.value.len());
+            let mut digest = CRC.digest();
+            digest.update(&self.value[start..end]);
+            let actual = digest.finalize();
+            if actual != expected {
+                return Err(ChunkChecksumMismatchError {
+                    chunk: index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of chunks a value of `value_size` bytes occupies under
+    /// `chunk_size_log`, i.e. the length of the on-disk chunk table.
+    fn chunk_count(value_size: usize, chunk_size_log: u8) -> usize {
+        if chunk_size_log == 0 {
+            return 0;
+        }
+        let chunk_size = 1usize << chunk_size_log;
+        value_size.div_ceil(chunk_size)
+    }
+
+    /// CRC64 over the [`LEGACY_VERSION`] field set, which predates the
+    /// encryption, compression, and nonce bytes. Used when verifying records
+    /// read back from a pre-header log.
+    pub fn calculate_checksum_legacy(&self) -> u64 {
+        let mut digest = CRC.digest();
+
         digest.update(if self.active { &[1] } else { &[0] });
         digest.update(&self.timestamp.to_le_bytes());
         digest.update(&self.key_size.to_le_bytes());
@@ -141,116 +502,1206 @@ impl Entry {
         digest.finalize()
     }
 
+    /// Whether the stored checksum matches a fresh digest under `version`'s
+    /// layout, telling an intact record from a torn final write.
+    fn matches_checksum(&mut self, version: u8) -> bool {
+        let actual = match version {
+            LEGACY_VERSION => self.calculate_checksum_legacy(),
+            VERSION_V1 => self.calculate_checksum_v1(),
+            _ => self.calculate_checksum(),
+        };
+        self.checksum == actual
+    }
+
+    /// Verify an entry read back under `version`'s layout: the entry CRC guards
+    /// the header (and, when chunked, the chunk table), then every value chunk
+    /// is re-checked against its stored CRC so corruption is pinpointed to a
+    /// region rather than the whole record.
+    fn verify(&mut self, version: u8) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.matches_checksum(version) {
+            let actual = match version {
+                LEGACY_VERSION => self.calculate_checksum_legacy(),
+                VERSION_V1 => self.calculate_checksum_v1(),
+                _ => self.calculate_checksum(),
+            };
+            return Err(Box::new(ChecksumMismatchError {
+                expected: self.checksum,
+                actual,
+            }));
+        }
+        if self.chunk_size_log != 0 {
+            self.verify_chunks()?;
+        }
+        Ok(())
+    }
+
+    /// Header fields bound as AEAD associated data, so a tampered header is
+    /// rejected by the tag check alongside the value itself. The stored
+    /// `value_size` is left out as it isn't known until sealing has happened.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut aad: Vec<u8> = Vec::new();
+        aad.push(if self.active { 1 } else { 0 });
+        aad.push(self.encryption);
+        aad.push(self.compression);
+        aad.extend_from_slice(&self.timestamp.to_le_bytes());
+        aad.extend_from_slice(&self.key_size.to_le_bytes());
+        aad.extend_from_slice(&self.key);
+        aad
+    }
+
     /// Converts the Entry struct into a Vec<u8> in little endian form.
     pub fn as_bytes(&mut self) -> Vec<u8> {
+        self.sealed_bytes(None, true)
+            .expect("plaintext serialization is infallible")
+    }
+
+    /// Compress the value with the requested codec, keeping the raw bytes when
+    /// the compressed form isn't actually smaller so tiny values aren't inflated.
+    fn compressed_value(&self) -> (u8, Vec<u8>) {
+        if self.compression == COMPRESSION_LZ4 {
+            let compressed = lz4_flex::compress_prepend_size(&self.value);
+            if compressed.len() < self.value.len() {
+                return (COMPRESSION_LZ4, compressed);
+            }
+        }
+        (COMPRESSION_NONE, self.value.clone())
+    }
+
+    /// Serialize the entry, compressing the value and optionally sealing it with
+    /// `crypto`.
+    ///
+    /// The value is compressed first (the `key` stays raw so index extractors can
+    /// read it); with a cipher configured the compressed bytes are then replaced
+    /// in place with their AEAD ciphertext under a fresh nonce. Unless disabled
+    /// (below), the CRC is recomputed over the stored bytes — compressed, then encrypted — so
+    /// corruption is caught before the costlier decompress/AEAD steps on read.
+    ///
+    /// With `checksum_enabled` false the CRC work is skipped entirely (a stored
+    /// `0` and no chunk table) for workloads that trust the storage underneath;
+    /// otherwise a non-zero `chunk_size_log` splits the stored value into a
+    /// per-chunk checksum table instead of hashing it whole.
+    pub fn sealed_bytes(
+        &mut self,
+        crypto: Option<&Crypto>,
+        checksum_enabled: bool,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (compression, stored) = self.compressed_value();
+        self.compression = compression;
+        self.value = stored;
+
+        if let Some(crypto) = crypto {
+            if crypto.encryption != EncryptionType::None {
+                self.encryption = crypto.encryption.as_byte();
+                OsRng.fill_bytes(&mut self.nonce);
+                let aad = self.associated_data();
+                let ciphertext = crypto.seal(&self.nonce, &self.value, &aad)?;
+                self.value = ciphertext;
+            }
+        }
+        self.value_size = self.value.len();
+
+        if checksum_enabled {
+            self.chunk_checksums = self.compute_chunk_checksums();
+            self.checksum = self.calculate_checksum();
+        } else {
+            self.chunk_size_log = 0;
+            self.chunk_checksums = Vec::new();
+            self.checksum = 0;
+        }
+
         let mut data: Vec<u8> = Vec::new();
-        let mut active = if self.active { vec![1] } else { vec![0] };
+        data.extend_from_slice(&self.checksum.to_le_bytes());
+        data.push(if self.active { 1 } else { 0 });
+        data.push(self.encryption);
+        data.push(self.compression);
+        data.push(self.chunk_size_log);
+        data.extend_from_slice(&self.timestamp.to_le_bytes());
+        data.extend_from_slice(&self.key_size.to_le_bytes());
+        data.extend_from_slice(&self.value_size.to_le_bytes());
+        data.extend_from_slice(&self.nonce);
+        for crc in &self.chunk_checksums {
+            data.extend_from_slice(&crc.to_le_bytes());
+        }
+        data.extend_from_slice(&self.key);
+        data.extend_from_slice(&self.value);
+
+        Ok(data)
+    }
 
-        data.append(&mut self.checksum.to_le_bytes().to_vec());
-        data.append(&mut active);
-        data.append(&mut self.timestamp.to_le_bytes().to_vec());
-        data.append(&mut self.key_size.to_le_bytes().to_vec());
-        data.append(&mut self.value_size.to_le_bytes().to_vec());
-        data.append(&mut self.key.clone());
-        data.append(&mut self.value.clone());
+    /// Re-emit an already-decoded record in the current layout without
+    /// re-compressing or re-encrypting its stored value, used by [`Writer::upgrade`]
+    /// to translate an older header in place. The compression/encryption flags,
+    /// nonce, and payload bytes are kept verbatim — only the header is rewritten
+    /// to the current layout (a zero `chunk_size_log`, no chunk table) and the
+    /// CRC recomputed to match.
+    fn reframe_bytes(&mut self) -> Vec<u8> {
+        self.chunk_size_log = 0;
+        self.chunk_checksums = Vec::new();
+        self.value_size = self.value.len();
+        self.checksum = self.calculate_checksum();
 
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(&self.checksum.to_le_bytes());
+        data.push(if self.active { 1 } else { 0 });
+        data.push(self.encryption);
+        data.push(self.compression);
+        data.push(self.chunk_size_log);
+        data.extend_from_slice(&self.timestamp.to_le_bytes());
+        data.extend_from_slice(&self.key_size.to_le_bytes());
+        data.extend_from_slice(&self.value_size.to_le_bytes());
+        data.extend_from_slice(&self.nonce);
+        data.extend_from_slice(&self.key);
+        data.extend_from_slice(&self.value);
         data
     }
 
-    /// Takes in a file and from the specific offset retrieves and builds an Entry struct
+    /// Decode one record at the reader's cursor using `version`'s layout,
+    /// dispatching to the matching reader so a log written by an older build
+    /// still round-trips. A version beyond [`FORMAT_VERSION`] is rejected.
+    pub fn from_reader_versioned(
+        file: &mut File,
+        version: u8,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        match version {
+            LEGACY_VERSION => Self::from_reader_legacy(file),
+            VERSION_V1 => Self::from_reader_v1(file),
+            FORMAT_VERSION => Self::from_reader(file),
+            other => Err(Box::new(UnsupportedVersionError(other))),
+        }
+    }
+
+    /// Decode a [`LEGACY_VERSION`] record: the original layout without the
+    /// encryption, compression, and nonce fields. Those are filled in as "none"
+    /// so the entry behaves like an unencrypted, uncompressed current record.
+    pub fn from_reader_legacy(file: &mut File) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut buf: [u8; 64] = [0; 64];
+
+        file.read_exact(&mut buf[0..8])?;
+        let checksum = u64::from_le_bytes(buf[0..8].try_into().unwrap());
+
+        file.read_exact(&mut buf[0..1])?;
+        let active = buf[0] == 1;
+
+        file.read_exact(&mut buf[0..16])?;
+        let timestamp = u128::from_le_bytes(buf[0..16].try_into().unwrap());
+
+        file.read_exact(&mut buf[0..8])?;
+        let key_size = usize::from_le_bytes(buf[0..8].try_into().unwrap());
+
+        file.read_exact(&mut buf[0..8])?;
+        let value_size = usize::from_le_bytes(buf[0..8].try_into().unwrap());
+
+        let mut key = Vec::new();
+        let mut value = Vec::new();
+        key.resize(key_size, 0);
+        value.resize(value_size, 0);
+
+        file.read_exact(&mut key[0..key_size])?;
+        file.read_exact(&mut value[0..value_size])?;
+
+        Ok(Entry {
+            checksum,
+            active,
+            encryption: EncryptionType::None.as_byte(),
+            compression: COMPRESSION_NONE,
+            chunk_size_log: 0,
+            timestamp,
+            key_size,
+            value_size,
+            nonce: [0u8; NONCE_LEN],
+            chunk_checksums: Vec::new(),
+            key,
+            value,
+        })
+    }
+
+    /// Decode a [`VERSION_V1`] record: the signed layout that predates the
+    /// chunk-size byte and per-chunk table, so those fields read as "unchunked".
+    pub fn from_reader_v1(file: &mut File) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut buf: [u8; 64] = [0; 64];
+
+        file.read_exact(&mut buf[0..8])?;
+        let checksum = u64::from_le_bytes(buf[0..8].try_into().unwrap());
+
+        file.read_exact(&mut buf[0..1])?;
+        let active = buf[0] == 1;
+
+        file.read_exact(&mut buf[0..1])?;
+        let encryption = buf[0];
+        EncryptionType::from_byte(encryption)?;
+
+        file.read_exact(&mut buf[0..1])?;
+        let compression = buf[0];
+
+        file.read_exact(&mut buf[0..16])?;
+        let timestamp = u128::from_le_bytes(buf[0..16].try_into().unwrap());
+
+        file.read_exact(&mut buf[0..8])?;
+        let key_size = usize::from_le_bytes(buf[0..8].try_into().unwrap());
+
+        file.read_exact(&mut buf[0..8])?;
+        let value_size = usize::from_le_bytes(buf[0..8].try_into().unwrap());
+
+        let mut nonce = [0u8; NONCE_LEN];
+        file.read_exact(&mut nonce)?;
+
+        let mut key = Vec::new();
+        let mut value = Vec::new();
+        key.resize(key_size, 0);
+        value.resize(value_size, 0);
+
+        file.read_exact(&mut key[0..key_size])?;
+        file.read_exact(&mut value[0..value_size])?;
+
+        Ok(Entry {
+            checksum,
+            active,
+            encryption,
+            compression,
+            chunk_size_log: 0,
+            timestamp,
+            key_size,
+            value_size,
+            nonce,
+            chunk_checksums: Vec::new(),
+            key,
+            value,
+        })
+    }
+
+    /// Decode one record in the current ([`FORMAT_VERSION`]) layout at the
+    /// reader's cursor, including the chunk-size byte and per-chunk checksum
+    /// table that sits between the nonce and the key/value region.
     pub fn from_reader(file: &mut File) -> Result<Self, Box<dyn std::error::Error>>  {
         let mut buf: [u8; 64] = [0; 64];
 
-        file.read(&mut buf[0..8])?;
+        file.read_exact(&mut buf[0..8])?;
         let checksum = u64::from_le_bytes(buf[0..8].try_into().unwrap());
-        
-        file.read(&mut buf[0..1])?;
-        let active = if  buf[0] == 1 { true } else { false }; 
 
-        file.read(&mut buf[0..16])?;
+        file.read_exact(&mut buf[0..1])?;
+        let active = buf[0] == 1;
+
+        file.read_exact(&mut buf[0..1])?;
+        let encryption = buf[0];
+        EncryptionType::from_byte(encryption)?;
+
+        file.read_exact(&mut buf[0..1])?;
+        let compression = buf[0];
+
+        file.read_exact(&mut buf[0..1])?;
+        let chunk_size_log = buf[0];
+        if chunk_size_log > MAX_CHUNK_SIZE_LOG {
+            return Err(Box::new(InvalidChunkSizeError(chunk_size_log)));
+        }
+
+        file.read_exact(&mut buf[0..16])?;
         let timestamp = u128::from_le_bytes(buf[0..16].try_into().unwrap());
 
-        file.read(&mut buf[0..8])?;
+        file.read_exact(&mut buf[0..8])?;
         let key_size = usize::from_le_bytes(buf[0..8].try_into().unwrap());
 
-        file.read(&mut buf[0..8])?;
+        file.read_exact(&mut buf[0..8])?;
         let value_size = usize::from_le_bytes(buf[0..8].try_into().unwrap());
 
+        let mut nonce = [0u8; NONCE_LEN];
+        file.read_exact(&mut nonce)?;
+
+        let mut chunk_checksums = Vec::new();
+        for _ in 0..Self::chunk_count(value_size, chunk_size_log) {
+            file.read_exact(&mut buf[0..8])?;
+            chunk_checksums.push(u64::from_le_bytes(buf[0..8].try_into().unwrap()));
+        }
+
         let mut key = Vec::new();
         let mut value = Vec::new();
         key.resize(key_size, 0);
         value.resize(value_size, 0);
 
-        file.read(&mut key[0..key_size])?;
-        file.read(&mut value[0..value_size])?;
+        file.read_exact(&mut key[0..key_size])?;
+        file.read_exact(&mut value[0..value_size])?;
 
         Ok(
             Entry {
                 checksum,
                 active,
+                encryption,
+                compression,
+                chunk_size_log,
                 timestamp,
                 key_size,
                 value_size,
+                nonce,
+                chunk_checksums,
                 key,
                 value
             }
         )
     }
 
+    /// Recover the original value in place: decrypt the stored region (a no-op
+    /// for plaintext entries) and then decompress it. Call only after the CRC has
+    /// been verified.
+    pub fn restore_value(
+        &mut self,
+        crypto: Option<&Crypto>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let region = if self.encryption == EncryptionType::None.as_byte() {
+            std::mem::take(&mut self.value)
+        } else {
+            let crypto =
+                crypto.ok_or(CryptoError("entry is encrypted but no passphrase was given"))?;
+            let aad = self.associated_data();
+            crypto.open(&self.nonce, &self.value, &aad)?
+        };
+        let value = decompress(self.compression, &region)?;
+        self.value_size = value.len();
+        self.value = value;
+        Ok(())
+    }
+
     /// Mark the entry as inactive so we can compact it later
     pub fn mark_inactive(&mut self) {
         self.active = false;
     }
 }
 
+/// Roll to a new data file once the active one passes this many bytes (128 MiB)
+const DEFAULT_MAX_FILE_SIZE: u64 = 128 * 1024 * 1024;
+
+/// Path of the numbered data file `id` within `dir`.
+fn data_path(dir: &str, id: usize) -> String {
+    format!("{}/{:010}.data", dir, id)
+}
+
+/// Path of the directory-level hint sidecar.
+fn hint_path(dir: &str) -> String {
+    format!("{}/index.hint", dir)
+}
+
+/// Path of the file holding the Argon2 salt shared by every data file.
+fn salt_path(dir: &str) -> String {
+    format!("{}/salt", dir)
+}
+
+/// Load the directory's Argon2 salt, generating and persisting one on first use.
+///
+/// Every data file shares the one salt so the same passphrase re-derives the
+/// identical key across reopens and compactions.
+fn read_or_create_salt(dir: &str) -> Result<SaltString, Box<dyn std::error::Error>> {
+    let path = salt_path(dir);
+    match std::fs::read(&path) {
+        Ok(bytes) => Ok(SaltString::from_b64(std::str::from_utf8(&bytes)?)
+            .map_err(|_| CryptoError("stored salt is not valid"))?),
+        Err(_) => {
+            let salt = SaltString::generate(&mut OsRng);
+            std::fs::write(&path, salt.as_str().as_bytes())?;
+            Ok(salt)
+        }
+    }
+}
+
+/// Sorted ids of the `*.data` files in `dir`.
+fn list_data_ids(dir: &str) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    let mut ids = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        if let Some(stem) = name.strip_suffix(".data") {
+            if let Ok(id) = stem.parse::<usize>() {
+                ids.push(id);
+            }
+        }
+    }
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Open data file `id` for appending, creating it if absent.
+fn open_data_rw(dir: &str, id: usize) -> Result<File, Box<dyn std::error::Error>> {
+    Ok(OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(data_path(dir, id))?)
+}
+
+/// Open data file `id` read-only (used by `get` and compaction scans).
+fn open_data_ro(dir: &str, id: usize) -> Result<File, Box<dyn std::error::Error>> {
+    Ok(OpenOptions::new().read(true).open(data_path(dir, id))?)
+}
+
+/// Write the signature + version header at offset 0 of a fresh file.
+fn write_header(file: &mut File) -> Result<(), Box<dyn std::error::Error>> {
+    file.seek(std::io::SeekFrom::Start(0))?;
+    file.write_all(&MAGIC)?;
+    file.write_all(&[FORMAT_VERSION])?;
+    Ok(())
+}
+
+/// Open data file `id` for appending, stamping a fresh header on a brand-new
+/// file and leaving the cursor at the end ready to append.
+fn init_data_file(dir: &str, id: usize) -> Result<File, Box<dyn std::error::Error>> {
+    let mut file = open_data_rw(dir, id)?;
+    if file.metadata()?.len() == 0 {
+        write_header(&mut file)?;
+    }
+    file.seek(std::io::SeekFrom::End(0))?;
+    Ok(file)
+}
+
+/// Sniff a data file's format: match the signature to read its version and the
+/// offset entries begin at, or fall back to [`LEGACY_VERSION`] starting at byte
+/// zero for a pre-header file. A future version is rejected outright.
+fn detect_version(file: &mut File) -> Result<(u8, u64), Box<dyn std::error::Error>> {
+    let len = file.metadata()?.len();
+    if len < HEADER_LEN {
+        return Ok((FORMAT_VERSION, 0));
+    }
+
+    file.seek(std::io::SeekFrom::Start(0))?;
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Ok((LEGACY_VERSION, 0));
+    }
+
+    let mut version = [0u8; 1];
+    file.read_exact(&mut version)?;
+    if version[0] > FORMAT_VERSION {
+        return Err(Box::new(UnsupportedVersionError(version[0])));
+    }
+    Ok((version[0], HEADER_LEN))
+}
+
+/// Replay data file `id` into `index`, keeping the newest `timestamp` per key
+/// and dropping keys whose newest entry is a tombstone. Records are decoded with
+/// `version`'s layout starting at `start` (past any file header). With `verify`
+/// set, a record whose stored `checksum` disagrees with a fresh digest is
+/// treated as a torn final write: the scan stops there. Returns the append offset (end of the last
+/// intact entry).
+fn recover_into(
+    index: &mut Index,
+    file: &mut File,
+    file_id: usize,
+    start: u64,
+    version: u8,
+    verify: bool,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let end = file.metadata()?.len();
+    let mut offset: u64 = start;
+    file.seek(std::io::SeekFrom::Start(start))?;
+
+    while offset < end {
+        let mut entry = match Entry::from_reader_versioned(file, version) {
+            Ok(entry) => entry,
+            Err(_) => break,
+        };
+        // A torn final write is still caught structurally (a short read fails to
+        // parse); the CRC gate is only consulted when checksums are enabled.
+        if verify && !entry.matches_checksum(version) {
+            break;
+        }
+        let size = file.stream_position()? - offset;
+
+        let newer = match index.lookup(entry.key.clone()) {
+            Ok(existing) => entry.timestamp >= existing.timestamp,
+            Err(_) => true,
+        };
+        if newer {
+            if entry.active {
+                index.update(
+                    entry.key.clone(),
+                    IndexValue::new(entry.timestamp, file_id, offset as usize, size as usize),
+                );
+            } else {
+                index.remove(&entry.key);
+            }
+        }
+
+        offset += size;
+    }
+
+    Ok(offset)
+}
+
+/// Dump the live index to a hint sidecar: `(key_size, key, file_id, offset, size, timestamp)`
+/// per key, enough to rebuild the index without reading values back.
+fn write_hints(path: &str, index: &Index) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(path)?;
+    for (key, value) in index.iter() {
+        file.write_all(&key.len().to_le_bytes())?;
+        file.write_all(key)?;
+        file.write_all(&value.file_id.to_le_bytes())?;
+        file.write_all(&value.offset.to_le_bytes())?;
+        file.write_all(&value.size.to_le_bytes())?;
+        file.write_all(&value.timestamp.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Rebuild an [`Index`] from a hint file written by [`write_hints`].
+///
+/// A truncated or garbled sidecar returns [`CorruptHintError`] rather than
+/// panicking, so the caller can fall back to a full replay.
+fn load_hints(path: &str) -> Result<Index, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+    let mut index = Index::new();
+    let mut pos = 0;
+
+    // Take the next `n` bytes, or bail if the sidecar is shorter than claimed.
+    let take = |pos: &mut usize, n: usize| -> Result<&[u8], CorruptHintError> {
+        let end = pos.checked_add(n).filter(|&end| end <= bytes.len());
+        match end {
+            Some(end) => {
+                let slice = &bytes[*pos..end];
+                *pos = end;
+                Ok(slice)
+            }
+            None => Err(CorruptHintError),
+        }
+    };
+
+    while pos < bytes.len() {
+        let key_size = usize::from_le_bytes(take(&mut pos, 8)?.try_into()?);
+        let key = take(&mut pos, key_size)?.to_vec();
+        let file_id = usize::from_le_bytes(take(&mut pos, 8)?.try_into()?);
+        let offset = usize::from_le_bytes(take(&mut pos, 8)?.try_into()?);
+        let size = usize::from_le_bytes(take(&mut pos, 8)?.try_into()?);
+        let timestamp = u128::from_le_bytes(take(&mut pos, 16)?.try_into()?);
+
+        index.update(key, IndexValue::new(timestamp, file_id, offset, size));
+    }
+
+    Ok(index)
+}
+
+/// The hint sidecar ended mid-record, so it can't be trusted
+#[derive(Debug)]
+pub struct CorruptHintError;
+impl Error for CorruptHintError {}
+impl fmt::Display for CorruptHintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "hint file is truncated or corrupt")
+    }
+}
+
+/// Whether the hint file exists and is at least as new as every data file, and
+/// so safe to trust instead of replaying them all.
+fn hints_fresh(dir: &str, ids: &[usize]) -> bool {
+    let hint = match std::fs::metadata(hint_path(dir)).and_then(|m| m.modified()) {
+        Ok(hint) => hint,
+        Err(_) => return false,
+    };
+    ids.iter().all(|&id| {
+        match std::fs::metadata(data_path(dir, id)).and_then(|m| m.modified()) {
+            Ok(data) => hint >= data,
+            Err(_) => false,
+        }
+    })
+}
+
+/// Read back the record an [`IndexValue`] points at, routing to its data file
+/// and decrypting the value so callers (extractors, compaction) see plaintext.
+fn read_entry(
+    dir: &str,
+    value: &IndexValue,
+    crypto: Option<&Crypto>,
+    version: u8,
+) -> Result<Entry, Box<dyn std::error::Error>> {
+    let mut file = open_data_ro(dir, value.file_id)?;
+    file.seek(std::io::SeekFrom::Start(value.offset as u64))?;
+    let mut entry = Entry::from_reader_versioned(&mut file, version)?;
+    entry.restore_value(crypto)?;
+    Ok(entry)
+}
+
+/// A key-extraction function for a secondary index, boxed so definitions with
+/// different closures can live side by side.
+pub type KeyExtractor = Box<dyn Fn(&Entry) -> Vec<Vec<u8>> + Send>;
+
+/// A user-defined secondary index: an extractor that maps an [`Entry`] to zero
+/// or more index keys, each mapping back to the entries that produced it.
+///
+/// Extractors must be deterministic and depend only on the entry, so the index
+/// can be rebuilt from the log during recovery.
+pub struct IndexDef {
+    pub name: String,
+    pub extractor: KeyExtractor,
+}
+
+impl IndexDef {
+    pub fn new(
+        name: impl Into<String>,
+        extractor: impl Fn(&Entry) -> Vec<Vec<u8>> + Send + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            extractor: Box::new(extractor),
+        }
+    }
+}
+
+/// A secondary index definition paired with its live `extracted key → pointers`
+/// map, maintained alongside the primary index on every `insert`/`delete`.
+struct SecondaryIndex {
+    def: IndexDef,
+    keys: HashMap<Vec<u8>, Vec<IndexValue>>,
+}
+
+impl SecondaryIndex {
+    fn new(def: IndexDef) -> Self {
+        Self {
+            def,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Record `value` under every key the extractor derives from `entry`.
+    fn insert(&mut self, entry: &Entry, value: IndexValue) {
+        for key in (self.def.extractor)(entry) {
+            self.keys.entry(key).or_default().push(value);
+        }
+    }
+
+    /// Drop `value` from every key the extractor derives from `entry`.
+    fn remove(&mut self, entry: &Entry, value: IndexValue) {
+        for key in (self.def.extractor)(entry) {
+            if let Some(values) = self.keys.get_mut(&key) {
+                values.retain(|existing| *existing != value);
+                if values.is_empty() {
+                    self.keys.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+/// Builder for a [`Writer`], exposing the at-rest knobs (encryption,
+/// compression) without churning the `new` signature every time one is added.
+pub struct WriterOptions {
+    passphrase: Option<String>,
+    encryption: EncryptionType,
+    compression: u8,
+    max_file_size: u64,
+    checksum_enabled: bool,
+    checksum_chunk_size_log: u8,
+}
+
+impl WriterOptions {
+    /// Defaults: no encryption, no compression, the standard rollover threshold,
+    /// a whole-entry CRC on every record (no chunk table).
+    pub fn new() -> Self {
+        Self {
+            passphrase: None,
+            encryption: EncryptionType::None,
+            compression: COMPRESSION_NONE,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            checksum_enabled: true,
+            checksum_chunk_size_log: 0,
+        }
+    }
+
+    /// Roll to a fresh active file once the current one grows past `bytes`.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Default compression applied to each value before it is appended
+    /// (`COMPRESSION_NONE` or `COMPRESSION_LZ4`). Values that don't shrink are
+    /// stored raw regardless, so the choice is effectively per-entry.
+    pub fn compression(mut self, compression: u8) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Encrypt values at rest with a key derived from `passphrase`, defaulting
+    /// the cipher to AES-GCM; pair with [`WriterOptions::encryption`] to pick
+    /// ChaCha20-Poly1305 instead.
+    pub fn passphrase(mut self, passphrase: String) -> Self {
+        self.passphrase = Some(passphrase);
+        if self.encryption == EncryptionType::None {
+            self.encryption = EncryptionType::AesGcm;
+        }
+        self
+    }
+
+    /// Choose which AEAD cipher to use when a passphrase is set.
+    pub fn encryption(mut self, encryption: EncryptionType) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Turn CRC checking on or off entirely. Disabling skips both writing and
+    /// verifying checksums, trading integrity for throughput on storage that is
+    /// already trusted to be sound.
+    pub fn checksum_enabled(mut self, enabled: bool) -> Self {
+        self.checksum_enabled = enabled;
+        self
+    }
+
+    /// Size of each value chunk in the per-entry checksum table, as a power of
+    /// two (so the chunk is `1 << log` bytes). `0` keeps a single whole-entry
+    /// CRC; any larger value splits the value into independently-checksummed
+    /// chunks so a mismatch is pinpointed to one region rather than the whole
+    /// record. A full `get` returns the entire value and so still re-checks
+    /// every chunk.
+    pub fn checksum_chunk_size_log(mut self, log: u8) -> Self {
+        self.checksum_chunk_size_log = log.min(MAX_CHUNK_SIZE_LOG);
+        self
+    }
+
+    /// Open the writer at `directory` with these options.
+    pub fn open(self, directory: String) -> Result<Writer, Box<dyn std::error::Error>> {
+        Writer::with_options(directory, self)
+    }
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[allow(dead_code)]
-/// Writes append only data to our log file and manages stale data
+/// Writes append only data across a directory of numbered files and manages
+/// stale data.
 pub struct Writer {
     index: Arc<Mutex<Index>>,
+    /// Named secondary indexes, each maintained from a user extractor
+    secondary: Arc<Mutex<Vec<SecondaryIndex>>>,
+    /// Handle to the active (newest) data file we append to
     file: Arc<Mutex<File>>,
+    /// Id of the active data file; older files have smaller ids
+    active_id: Arc<Mutex<usize>>,
+    /// Detected on-disk format version per data file, so reads decode each with
+    /// the layout it was written in until an `upgrade` rewrites it.
+    versions: Arc<Mutex<HashMap<usize, u8>>>,
     directory: String,
+    /// Derived AEAD key, present only when opened with a passphrase
+    crypto: Option<Crypto>,
+    /// Default per-value compression mode
+    compression: u8,
+    max_file_size: u64,
+    /// Whether records carry (and reads verify) a CRC at all
+    checksum_enabled: bool,
+    /// Log-2 of the per-entry checksum chunk size; `0` keeps a whole-entry CRC
+    checksum_chunk_size_log: u8,
 }
 
 impl Writer {
-    pub fn new(directory: String) -> Result<Self, Box<dyn std::error::Error>> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&directory)?;
-    
+    /// Open a writer rooted at `directory`, encrypting entries at rest when a
+    /// `passphrase` is supplied (otherwise they are stored in plaintext).
+    pub fn new(
+        directory: String,
+        passphrase: Option<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = WriterOptions::new();
+        if let Some(passphrase) = passphrase {
+            options = options.passphrase(passphrase);
+        }
+        options.open(directory)
+    }
+
+    /// Open a writer from a prepared [`WriterOptions`] builder.
+    pub fn with_options(
+        directory: String,
+        options: WriterOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(&directory)?;
+
+        // Derive the at-rest key once, persisting a fresh salt alongside the data
+        // so a reopen with the same passphrase re-derives the identical key.
+        let crypto = match &options.passphrase {
+            Some(passphrase) => {
+                let salt = read_or_create_salt(&directory)?;
+                Some(Crypto::derive(passphrase, &salt, options.encryption)?)
+            }
+            None => None,
+        };
+
+        // Enumerate existing data files; an empty directory starts at file 0.
+        let mut ids = list_data_ids(&directory)?;
+        if ids.is_empty() {
+            init_data_file(&directory, 0)?;
+            ids.push(0);
+        }
+
+        // Sniff each file's format up front so recovery and later reads decode
+        // every record with the layout it was actually written in.
+        let mut versions = HashMap::new();
+        for &id in &ids {
+            let mut file = open_data_ro(&directory, id)?;
+            versions.insert(id, detect_version(&mut file)?.0);
+        }
+
+        // Rebuild the index from a fresh hint sidecar when we can, otherwise
+        // replay every data file so a restart doesn't lose its key→offset map.
+        let mut index = Index::new();
+        let checksum_enabled = options.checksum_enabled;
+        let replay = |index: &mut Index| -> Result<(), Box<dyn std::error::Error>> {
+            for &id in &ids {
+                let mut file = open_data_ro(&directory, id)?;
+                let (version, start) = detect_version(&mut file)?;
+                recover_into(index, &mut file, id, start, version, checksum_enabled)?;
+            }
+            Ok(())
+        };
+        if hints_fresh(&directory, &ids) {
+            // A hint that points at a data file no longer on disk is stale and
+            // would leave the index with dangling pointers; replay instead.
+            match load_hints(&hint_path(&directory)) {
+                Ok(loaded) if loaded.iter().all(|(_, v)| ids.contains(&v.file_id)) => {
+                    index = loaded
+                }
+                _ => replay(&mut index)?,
+            }
+        } else {
+            replay(&mut index)?;
+        }
+
+        // Never mix layouts within one file: if the newest file predates the
+        // current format, roll appends onto a fresh current-format file and
+        // leave the old ones read-only until `upgrade` rewrites them.
+        let mut active_id = *ids.last().unwrap();
+        if versions.get(&active_id).copied() != Some(FORMAT_VERSION) {
+            active_id += 1;
+            init_data_file(&directory, active_id)?;
+        }
+        versions.insert(active_id, FORMAT_VERSION);
+
+        // Appends continue at the end of the active file.
+        let file = init_data_file(&directory, active_id)?;
+
         Ok(
             Self {
-                index: Arc::new(Mutex::new(Index::new())),
+                index: Arc::new(Mutex::new(index)),
+                secondary: Arc::new(Mutex::new(Vec::new())),
                 file: Arc::new(Mutex::new(file)),
+                active_id: Arc::new(Mutex::new(active_id)),
+                versions: Arc::new(Mutex::new(versions)),
                 directory,
+                crypto,
+                compression: options.compression,
+                max_file_size: options.max_file_size,
+                checksum_enabled: options.checksum_enabled,
+                checksum_chunk_size_log: options.checksum_chunk_size_log,
             }
         )
     }
 
-    pub fn insert(&mut self, entry: Entry) -> Result<(), Box<dyn std::error::Error>> {
+    /// Rewrite every older-format data file under `directory` into the current
+    /// layout, one file at a time: drain its records through the matching reader
+    /// into a sibling `.upgrade` file stamped with the current header, then
+    /// rename it over the original so a crash leaves either the old or the new
+    /// file intact, never a half-written one. The hint sidecar is dropped
+    /// afterward since offsets shift, forcing the next open to replay.
+    pub fn upgrade(directory: &str) -> Result<(), Box<dyn std::error::Error>> {
+        for id in list_data_ids(directory)? {
+            let mut source = open_data_ro(directory, id)?;
+            let (version, start) = detect_version(&mut source)?;
+            if version == FORMAT_VERSION {
+                continue;
+            }
+
+            let temp = format!("{}.upgrade", data_path(directory, id));
+            let mut out = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&temp)?;
+            write_header(&mut out)?;
+            out.seek(std::io::SeekFrom::End(0))?;
+
+            let end = source.metadata()?.len();
+            source.seek(std::io::SeekFrom::Start(start))?;
+            let mut offset = start;
+            while offset < end {
+                let mut entry = match Entry::from_reader_versioned(&mut source, version) {
+                    Ok(entry) => entry,
+                    Err(_) => break,
+                };
+                if !entry.matches_checksum(version) {
+                    break;
+                }
+                offset = source.stream_position()?;
+                out.write_all(&entry.reframe_bytes())?;
+            }
+            out.flush()?;
+
+            std::fs::rename(&temp, data_path(directory, id))?;
+        }
+
+        let _ = std::fs::remove_file(hint_path(directory));
+        Ok(())
+    }
+
+    /// The format version data file `file_id` was written in, defaulting to the
+    /// current version for files this writer created.
+    fn version_of(&self, file_id: usize) -> u8 {
+        self.versions
+            .lock()
+            .unwrap()
+            .get(&file_id)
+            .copied()
+            .unwrap_or(FORMAT_VERSION)
+    }
+
+    /// Read back the record an index pointer refers to, decoding it with its
+    /// file's format version and returning the plaintext value.
+    fn read_indexed(&self, value: &IndexValue) -> Result<Entry, Box<dyn std::error::Error>> {
+        read_entry(
+            &self.directory,
+            value,
+            self.crypto.as_ref(),
+            self.version_of(value.file_id),
+        )
+    }
+
+    /// Serialize an entry for the log, applying this writer's default
+    /// compression and encryption.
+    fn serialize(&self, entry: &mut Entry) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if entry.compression == COMPRESSION_NONE {
+            entry.compression = self.compression;
+        }
+        if self.checksum_enabled {
+            entry.chunk_size_log = self.checksum_chunk_size_log;
+        }
+        entry.sealed_bytes(self.crypto.as_ref(), self.checksum_enabled)
+    }
+
+    /// Persist a hint sidecar for the current index so the next open can skip a
+    /// full log replay.
+    pub fn checkpoint(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let index = self.index.lock().unwrap();
+        write_hints(&hint_path(&self.directory), &index)
+    }
+
+    /// Register a named secondary index and backfill it from the live log so it
+    /// reflects every key already present, the same way startup replay rebuilds
+    /// the primary index.
+    pub fn register_index(&mut self, def: IndexDef) -> Result<(), Box<dyn std::error::Error>> {
         let index = self.index.lock().unwrap();
+        let mut secondary = SecondaryIndex::new(def);
+        for (_key, value) in index.iter() {
+            let entry = self.read_indexed(value)?;
+            secondary.insert(&entry, *value);
+        }
+        self.secondary.lock().unwrap().push(secondary);
+        Ok(())
+    }
+
+    /// Query a named secondary index, returning every live pointer recorded for
+    /// `key` (empty when the index or key is unknown).
+    pub fn lookup_by(&self, index_name: &str, key: &[u8]) -> Vec<IndexValue> {
+        let secondary = self.secondary.lock().unwrap();
+        secondary
+            .iter()
+            .find(|s| s.def.name == index_name)
+            .and_then(|s| s.keys.get(key).cloned())
+            .unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, mut entry: Entry) -> Result<(), Box<dyn std::error::Error>> {
+        let mut index = self.index.lock().unwrap();
+        let mut secondary = self.secondary.lock().unwrap();
         let mut file = self.file.lock().unwrap();
+        let mut active_id = self.active_id.lock().unwrap();
 
-        // let data = entry.as_bytes();
-        // index.update("Hello".as_bytes().to_vec(), IndexValue::new(0, 0, 0, data.len()));
-        // file.write_all(&entry.clone().as_bytes()).unwrap();
+        // Retire the prior version's secondary-index entries before it is
+        // shadowed, so stale extracted keys don't linger.
+        if let Ok(old) = index.lookup(entry.key.clone()) {
+            if !secondary.is_empty() {
+                let old_entry = self.read_indexed(&old)?;
+                for s in secondary.iter_mut() {
+                    s.remove(&old_entry, old);
+                }
+            }
+        }
+
+        // Run extractors on the plaintext entry before it is (maybe) sealed.
+        let secondary_keys: Vec<Vec<Vec<u8>>> = secondary
+            .iter()
+            .map(|s| (s.def.extractor)(&entry))
+            .collect();
+
+        // Append the new record to the active file and index its location. The
+        // prior version (if any) becomes dead weight reclaimed by `compact`.
+        let offset = file.seek(std::io::SeekFrom::End(0))?;
+        let data = self.serialize(&mut entry)?;
+        file.write_all(&data)?;
+        let value = IndexValue::new(entry.timestamp, *active_id, offset as usize, data.len());
+        index.update(entry.key.clone(), value);
+        for (s, keys) in secondary.iter_mut().zip(secondary_keys) {
+            for key in keys {
+                s.keys.entry(key).or_default().push(value);
+            }
+        }
+
+        // Roll to a fresh active file once this one crosses the threshold.
+        if offset + data.len() as u64 >= self.max_file_size {
+            let next = *active_id + 1;
+            *file = init_data_file(&self.directory, next)?;
+            *active_id = next;
+            self.versions.lock().unwrap().insert(next, FORMAT_VERSION);
+        }
 
-        match index.lookup(entry.key.clone()) {
-            Ok(value) => {
-                let _ = file.seek(std::io::SeekFrom::Start(value.offset as u64)).unwrap();
-                
-                let mut found_entry = Entry::from_reader(&mut file)?;
-                found_entry.mark_inactive();
+        Ok(())
+    }
 
-                println!("Found entry: {:?}", found_entry);
+    /// Append a tombstone for `key` and drop it from the index, so the delete
+    /// survives a restart and is garbage-collected by the next `compact`.
+    pub fn delete(&mut self, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut index = self.index.lock().unwrap();
+        let mut secondary = self.secondary.lock().unwrap();
+        let mut file = self.file.lock().unwrap();
 
-            },
-            Err(_) => {
-                println!("New entry: {:?}", entry);
+        let old = match index.lookup(key.to_vec()) {
+            Ok(value) => value,
+            Err(_) => return Ok(()),
+        };
 
-                file.write_all(&entry.clone().as_bytes()).unwrap();
+        // Purge the key's secondary-index entries before the tombstone lands.
+        if !secondary.is_empty() {
+            let old_entry = self.read_indexed(&old)?;
+            for s in secondary.iter_mut() {
+                s.remove(&old_entry, old);
             }
+        }
+
+        let mut tombstone = Entry::new(key.to_vec(), Vec::new());
+        tombstone.mark_inactive();
+        let data = self.serialize(&mut tombstone)?;
+
+        file.seek(std::io::SeekFrom::End(0))?;
+        file.write_all(&data)?;
+        index.remove(key);
+
+        Ok(())
+    }
+
+    /// Fetch the value for `key`, or `Ok(None)` when it is absent or tombstoned.
+    ///
+    /// Routes to the data file recorded in the index, seeks to the record,
+    /// reconstructs it, and (unless checksums are disabled for this writer)
+    /// verifies its CRC against a fresh checksum before handing the bytes back.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let index = self.index.lock().unwrap();
+
+        let value = match index.lookup(key.to_vec()) {
+            Ok(value) => value,
+            Err(_) => return Ok(None),
         };
+        drop(index);
+
+        let version = self.version_of(value.file_id);
+        let mut file = open_data_ro(&self.directory, value.file_id)?;
+        file.seek(std::io::SeekFrom::Start(value.offset as u64))?;
+        let mut entry = Entry::from_reader_versioned(&mut file, version)?;
+
+        // Verify the record (entry CRC plus any chunk table) unless checksums
+        // were disabled for this writer, in which case we trust it as read.
+        if self.checksum_enabled {
+            entry.verify(version)?;
+        }
+
+        if !entry.active {
+            return Ok(None);
+        }
+
+        // Decrypt only after the CRC clears, so tampering is caught cheaply first.
+        entry.restore_value(self.crypto.as_ref())?;
+
+        Ok(Some(entry.value))
+    }
+
+    /// Compact the immutable data files: copy the newest active record for each
+    /// key they still own into a fresh merge file, rewrite the index offsets,
+    /// regenerate the hint sidecar, and delete the now-dead source files.
+    ///
+    /// The active file is left untouched so appends may proceed concurrently.
+    pub fn compact(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Reserve the merge file strictly above every existing id and roll the
+        // active writer past it, taking the `file` and `active_id` locks in the
+        // same order `insert` does. This stops a concurrent rotation from
+        // handing out `active_id + 1` and clobbering the compaction output.
+        let merge_id;
+        let stale: Vec<usize>;
+        {
+            let mut file = self.file.lock().unwrap();
+            let mut active_id = self.active_id.lock().unwrap();
+            stale = list_data_ids(&self.directory)?
+                .into_iter()
+                .filter(|&id| id < *active_id)
+                .collect();
+            if stale.is_empty() {
+                return Ok(());
+            }
+            let highest = list_data_ids(&self.directory)?
+                .into_iter()
+                .max()
+                .unwrap_or(*active_id)
+                .max(*active_id);
+            merge_id = highest + 1;
+            let new_active = merge_id + 1;
+            *file = init_data_file(&self.directory, new_active)?;
+            self.versions.lock().unwrap().insert(new_active, FORMAT_VERSION);
+            *active_id = new_active;
+        }
+
+        let mut merged = init_data_file(&self.directory, merge_id)?;
+        let mut merge_offset = merged.stream_position()?;
+        self.versions.lock().unwrap().insert(merge_id, FORMAT_VERSION);
+
+        let mut index = self.index.lock().unwrap();
+
+        // Keys whose live entry still lives in a stale file survive; newer writes
+        // and tombstoned keys are already accounted for in the index.
+        let survivors: Vec<(Vec<u8>, IndexValue)> = index
+            .iter()
+            .filter(|(_, value)| stale.contains(&value.file_id))
+            .map(|(key, value)| (key.clone(), *value))
+            .collect();
+
+        for (key, value) in survivors {
+            let mut entry = self.read_indexed(&value)?;
+            let data = self.serialize(&mut entry)?;
+            merged.write_all(&data)?;
+            index.update(
+                key,
+                IndexValue::new(value.timestamp, merge_id, merge_offset as usize, data.len()),
+            );
+            merge_offset += data.len() as u64;
+        }
+        merged.flush()?;
+
+        // Rebuild the secondary indexes against the rewritten pointers so they
+        // don't reference the soon-to-be-deleted files.
+        let mut secondary = self.secondary.lock().unwrap();
+        if !secondary.is_empty() {
+            for s in secondary.iter_mut() {
+                s.keys.clear();
+            }
+            for (_key, value) in index.iter() {
+                let entry = self.read_indexed(value)?;
+                for s in secondary.iter_mut() {
+                    s.insert(&entry, *value);
+                }
+            }
+        }
+        drop(secondary);
+
+        // Regenerate the hint sidecar to match the compacted layout, then drop
+        // the stale files.
+        write_hints(&hint_path(&self.directory), &index)?;
+        drop(index);
+        let mut versions = self.versions.lock().unwrap();
+        for id in &stale {
+            std::fs::remove_file(data_path(&self.directory, *id))?;
+            versions.remove(id);
+        }
 
         Ok(())
     }
@@ -263,23 +1714,18 @@ mod tests {
     #[test]
     fn index_update() {
         let mut index = Index::new();
-        assert_eq!(
-            index.update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 5, 0)).is_none(),
-            true
-        );
-        assert_eq!(
-            index.update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 10, 100)).is_some(),
-            true
-        );
+        assert!(index
+            .update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 5, 0))
+            .is_none());
+        assert!(index
+            .update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 10, 100))
+            .is_some());
     }
 
     #[test]
     fn index_lookup() {
         let mut index = Index::new();
-        assert_eq!(
-            index.lookup(vec![0, 1, 2, 3, 4]).is_err(),
-            true,
-        );
+        assert!(index.lookup(vec![0, 1, 2, 3, 4]).is_err());
         index.update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 5, 0));
         assert_eq!(
             index.lookup(vec![0, 1, 2, 3, 4]).unwrap(),
@@ -294,9 +1740,7 @@ mod tests {
         let mut entry = Entry::new(key.clone(), value);
         let mut other_entry = Entry::new(key, "Toted".as_bytes().to_vec());
 
-        println!("{:?}", entry.as_bytes());
-
-        // Compare to another object 
+        // Compare to another object
         let checksum = entry.calculate_checksum();
         assert_ne!(checksum, other_entry.calculate_checksum());   
 
@@ -307,11 +1751,213 @@ mod tests {
 
     #[test]
     fn writer_can_write() {
-        let mut writer = Writer::new("/tmp/yoted".to_string()).expect("Should open a writer");
+        let mut writer = Writer::new("/tmp/yoted".to_string(), None).expect("Should open a writer");
 
         let key = "Hello".as_bytes().to_vec();
         let value = "Yoted".as_bytes().to_vec();
         let entry = Entry::new(key, value);
         writer.insert(entry).expect("Can insert an entry");
     }
+
+    #[test]
+    fn writer_reads_back() {
+        let path = "/tmp/bitcask_get";
+        let _ = std::fs::remove_dir_all(path);
+
+        let mut writer = Writer::new(path.to_string(), None).expect("Should open a writer");
+
+        let key = "Hello".as_bytes().to_vec();
+        let value = "Yoted".as_bytes().to_vec();
+        writer
+            .insert(Entry::new(key.clone(), value.clone()))
+            .expect("Can insert an entry");
+
+        assert_eq!(writer.get(&key).expect("Can read the entry"), Some(value));
+        assert_eq!(
+            writer.get("missing".as_bytes()).expect("Absent key is Ok(None)"),
+            None
+        );
+    }
+
+    #[test]
+    fn writer_deletes() {
+        let path = "/tmp/bitcask_delete";
+        let _ = std::fs::remove_dir_all(path);
+
+        let mut writer = Writer::new(path.to_string(), None).expect("Should open a writer");
+
+        let key = "Hello".as_bytes().to_vec();
+        let value = "Yoted".as_bytes().to_vec();
+        writer
+            .insert(Entry::new(key.clone(), value.clone()))
+            .expect("Can insert an entry");
+
+        writer.delete(&key).expect("Can delete an entry");
+        assert_eq!(
+            writer.get(&key).expect("Deleted key reads back as Ok(None)"),
+            None
+        );
+    }
+
+    #[test]
+    fn writer_secondary_index() {
+        let path = "/tmp/bitcask_secondary";
+        let _ = std::fs::remove_dir_all(path);
+
+        let mut writer = Writer::new(path.to_string(), None).expect("Should open a writer");
+
+        // Index entries by the first byte of their key.
+        writer
+            .register_index(IndexDef::new("prefix", |entry: &Entry| {
+                entry.key.first().map(|b| vec![*b]).into_iter().collect()
+            }))
+            .expect("Can register a secondary index");
+
+        writer
+            .insert(Entry::new(b"apple".to_vec(), b"1".to_vec()))
+            .expect("Can insert an entry");
+        writer
+            .insert(Entry::new(b"avocado".to_vec(), b"2".to_vec()))
+            .expect("Can insert an entry");
+        writer
+            .insert(Entry::new(b"banana".to_vec(), b"3".to_vec()))
+            .expect("Can insert an entry");
+
+        assert_eq!(writer.lookup_by("prefix", b"a").len(), 2);
+        assert_eq!(writer.lookup_by("prefix", b"b").len(), 1);
+
+        // Deleting prunes the key from the secondary index too.
+        writer.delete(b"banana").expect("Can delete an entry");
+        assert_eq!(writer.lookup_by("prefix", b"b").len(), 0);
+    }
+
+    #[test]
+    fn writer_encrypts_round_trip() {
+        let path = "/tmp/bitcask_encrypted";
+        let _ = std::fs::remove_dir_all(path);
+
+        let mut writer = Writer::new(path.to_string(), Some("hunter2".to_string()))
+            .expect("Should open an encrypted writer");
+
+        let key = "Hello".as_bytes().to_vec();
+        let value = "a secret worth sealing".as_bytes().to_vec();
+        writer
+            .insert(Entry::new(key.clone(), value.clone()))
+            .expect("Can insert an encrypted entry");
+
+        assert_eq!(
+            writer.get(&key).expect("Found and decrypted the entry"),
+            Some(value)
+        );
+    }
+
+    #[test]
+    fn writer_compresses_round_trip() {
+        let path = "/tmp/bitcask_compressed";
+        let _ = std::fs::remove_dir_all(path);
+
+        let mut writer = WriterOptions::new()
+            .compression(COMPRESSION_LZ4)
+            .open(path.to_string())
+            .expect("Should open a compressing writer");
+
+        let key = "Hello".as_bytes().to_vec();
+        // Highly compressible payload so the LZ4 form actually wins
+        let value = vec![b'z'; 4096];
+        writer
+            .insert(Entry::new(key.clone(), value.clone()))
+            .expect("Can insert a compressible entry");
+
+        assert_eq!(
+            writer.get(&key).expect("Found and decompressed the entry"),
+            Some(value)
+        );
+    }
+
+    #[test]
+    fn writer_chunked_checksums_round_trip() {
+        let path = "/tmp/bitcask_chunked";
+        let _ = std::fs::remove_dir_all(path);
+
+        // A 16-byte chunk size splits this value across several table entries.
+        let mut writer = WriterOptions::new()
+            .checksum_chunk_size_log(4)
+            .open(path.to_string())
+            .expect("Should open a chunk-checksummed writer");
+
+        let key = "Hello".as_bytes().to_vec();
+        let value = vec![b'z'; 100];
+        writer
+            .insert(Entry::new(key.clone(), value.clone()))
+            .expect("Can insert a chunked entry");
+
+        assert_eq!(
+            writer.get(&key).expect("Found and verified the chunked entry"),
+            Some(value.clone())
+        );
+
+        // The chunk table survives a reopen and still verifies on read.
+        let reopened = Writer::new(path.to_string(), None).expect("Should reopen the writer");
+        assert_eq!(reopened.get(&key).expect("Recovered the chunked entry"), Some(value));
+    }
+
+    #[test]
+    fn writer_without_checksums_round_trip() {
+        let path = "/tmp/bitcask_no_checksum";
+        let _ = std::fs::remove_dir_all(path);
+
+        let mut writer = WriterOptions::new()
+            .checksum_enabled(false)
+            .open(path.to_string())
+            .expect("Should open a checksum-free writer");
+
+        let key = "Hello".as_bytes().to_vec();
+        let value = "Yoted".as_bytes().to_vec();
+        writer
+            .insert(Entry::new(key.clone(), value.clone()))
+            .expect("Can insert without a checksum");
+
+        assert_eq!(writer.get(&key).expect("Reads back unverified"), Some(value.clone()));
+
+        // Reopened the same way (checksums off), the CRC-less record still
+        // recovers — replay relies on the structural parse, not the checksum.
+        let reopened = WriterOptions::new()
+            .checksum_enabled(false)
+            .open(path.to_string())
+            .expect("Should reopen the writer");
+        assert_eq!(reopened.get(&key).expect("Recovered the entry"), Some(value));
+    }
+
+    #[test]
+    fn writer_upgrades_legacy_format() {
+        let path = "/tmp/bitcask_upgrade";
+        let _ = std::fs::remove_dir_all(path);
+        std::fs::create_dir_all(path).unwrap();
+
+        // Hand-write a single record in the pre-header (legacy) layout.
+        let key = b"Hello".to_vec();
+        let value = b"Yoted".to_vec();
+        let mut entry = Entry::new(key.clone(), value.clone());
+        entry.checksum = entry.calculate_checksum_legacy();
+
+        let mut legacy = Vec::new();
+        legacy.extend_from_slice(&entry.checksum.to_le_bytes());
+        legacy.push(1);
+        legacy.extend_from_slice(&entry.timestamp.to_le_bytes());
+        legacy.extend_from_slice(&entry.key_size.to_le_bytes());
+        legacy.extend_from_slice(&entry.value_size.to_le_bytes());
+        legacy.extend_from_slice(&key);
+        legacy.extend_from_slice(&value);
+        std::fs::write(format!("{}/{:010}.data", path, 0), &legacy).unwrap();
+
+        Writer::upgrade(path).expect("Can upgrade a legacy log");
+
+        // The rewritten file now carries the current signature.
+        let on_disk = std::fs::read(format!("{}/{:010}.data", path, 0)).unwrap();
+        assert_eq!(&on_disk[0..MAGIC.len()], &MAGIC);
+
+        // And the value survives a reopen in the current format.
+        let writer = Writer::new(path.to_string(), None).expect("Should reopen the upgraded log");
+        assert_eq!(writer.get(&key).expect("Found the upgraded entry"), Some(value));
+    }
 }
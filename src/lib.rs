@@ -8,23 +8,18 @@ mod tests {
     #[test]
     fn index_update() {
         let mut index = Index::new();
-        assert_eq!(
-            index.update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 5, 0)).is_none(),
-            true
-        );
-        assert_eq!(
-            index.update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 10, 100)).is_some(),
-            true
-        );
+        assert!(index
+            .update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 5, 0))
+            .is_none());
+        assert!(index
+            .update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 10, 100))
+            .is_some());
     }
 
     #[test]
     fn index_lookup() {
         let mut index = Index::new();
-        assert_eq!(
-            index.lookup(vec![0, 1, 2, 3, 4]).is_err(),
-            true,
-        );
+        assert!(index.lookup(vec![0, 1, 2, 3, 4]).is_err());
         index.update(vec![0, 1, 2, 3, 4], IndexValue::new(0, 0, 5, 0));
         assert_eq!(
             index.lookup(vec![0, 1, 2, 3, 4]).unwrap(),
@@ -39,9 +34,7 @@ mod tests {
         let mut entry = Entry::new(key.clone(), value);
         let mut other_entry = Entry::new(key, "Toted".as_bytes().to_vec());
 
-        println!("{:?}", entry.as_bytes());
-
-        // Compare to another object 
+        // Compare to another object
         let checksum = entry.calculate_checksum();
         assert_ne!(checksum, other_entry.calculate_checksum());   
 
@@ -52,7 +45,7 @@ mod tests {
 
     #[test]
     fn writer_can_write() {
-        let mut writer = Writer::new("/tmp/yoted".to_string()).expect("Should open a writer");
+        let mut writer = Writer::new("/tmp/yoted".to_string(), None).expect("Should open a writer");
 
         let key = "Hello".as_bytes().to_vec();
         let value = "Yoted".as_bytes().to_vec();